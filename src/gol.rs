@@ -1,9 +1,12 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
 use std::ops::{Index, IndexMut};
 
 use glium::texture::RawImage2d;
 use rand::{thread_rng, RngCore};
 
-/// Conway's Game of Life.
+/// Conway's Game of Life, and any other Life-like cellular automaton
+/// expressible in B/S notation.
 #[derive(Clone)]
 pub struct GoL {
     /// Linear vector of all cells on the board. Cells are 0 when dead. Non-zero
@@ -11,15 +14,76 @@ pub struct GoL {
     pub buffer: Vec<u8>,
     pub width: i32,
     pub height: i32,
+    /// `birth[n]` is true when a dead cell with `n` live neighbors comes alive.
+    birth: [bool; 9],
+    /// `survival[n]` is true when a live cell with `n` live neighbors stays alive.
+    survival: [bool; 9],
+}
+
+/// Conway's Game of Life: a cell is born with exactly 3 neighbors and
+/// survives with 2 or 3.
+pub const DEFAULT_RULE: &str = "B3/S23";
+
+/// Parse a Life-like rule string in B/S notation, e.g. `"B3/S23"` (Conway's
+/// Life), `"B36/S23"` (HighLife) or `"B2/S"` (Seeds), into birth and
+/// survival tables indexed by neighbor count (0-8).
+pub fn parse_rule(rule: &str) -> Result<([bool; 9], [bool; 9]), String> {
+    let mut birth = [false; 9];
+    let mut survival = [false; 9];
+
+    for segment in rule.split('/') {
+        let mut chars = segment.chars();
+        let kind = chars
+            .next()
+            .ok_or_else(|| format!("Empty rule segment in \"{}\"", rule))?;
+
+        let table = match kind.to_ascii_uppercase() {
+            'B' => &mut birth,
+            'S' => &mut survival,
+            _ => return Err(format!("Rule segment \"{}\" must start with B or S", segment)),
+        };
+
+        for digit in chars {
+            let n = digit
+                .to_digit(10)
+                .ok_or_else(|| format!("\"{}\" is not a valid neighbor count", digit))?
+                as usize;
+
+            if n > 8 {
+                return Err(format!("Neighbor count {} is out of range 0-8", n));
+            }
+
+            table[n] = true;
+        }
+    }
+
+    Ok((birth, survival))
 }
 
 impl GoL {
     pub fn new(dims: (usize, usize)) -> GoL {
-        GoL {
+        GoL::with_rule(dims, DEFAULT_RULE).expect("DEFAULT_RULE is a valid rule string")
+    }
+
+    /// Create a board that plays by the given Life-like rule, e.g. `"B3/S23"`.
+    pub fn with_rule(dims: (usize, usize), rule: &str) -> Result<GoL, String> {
+        let (birth, survival) = parse_rule(rule)?;
+
+        Ok(GoL {
             buffer: vec![0; dims.0 * dims.1],
             width: dims.0 as i32,
             height: dims.1 as i32,
-        }
+            birth: birth,
+            survival: survival,
+        })
+    }
+
+    /// Switch to a different Life-like rule without resetting the board.
+    pub fn set_rule(&mut self, rule: &str) -> Result<(), String> {
+        let (birth, survival) = parse_rule(rule)?;
+        self.birth = birth;
+        self.survival = survival;
+        Ok(())
     }
 
     /// Reset and randomize all cells.
@@ -83,14 +147,12 @@ impl GoL {
     #[inline]
     fn automata_rules(&self, x: i32, y: i32) -> u8 {
         let current_state = self[(x, y)];
-        let n_neighbors = self.alive_neighbors(x, y);
-
-        let next_state = match (n_neighbors, current_state != 0) {
-            (0..=1, true) => false, // Underpopulated
-            (2..=3, true) => true,  // Goldilocks zone
-            (3..=8, true) => false, // Overcrowded
-            (3, false) => true,     // Spontaneous reproduction
-            _ => false,             // From nothing comes nothing
+        let n_neighbors = self.alive_neighbors(x, y) as usize;
+
+        let next_state = if current_state != 0 {
+            self.survival[n_neighbors]
+        } else {
+            self.birth[n_neighbors]
         };
 
         if next_state {
@@ -113,6 +175,19 @@ impl GoL {
         .fold(0, |total, &neighbor| total + (neighbor != 0) as u8)
     }
 
+    /// Hash of which cells are alive, ignoring age. Two generations with the
+    /// same live cells hash equally even if their ages differ, which is what
+    /// period detection needs to recognize still lifes and oscillators.
+    pub fn alive_hash(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+
+        for &cell in &self.buffer {
+            (cell != 0).hash(&mut hasher);
+        }
+
+        hasher.finish()
+    }
+
     /// Convert to an image for use by Glium.
     pub fn as_raw_image_2d(&self) -> RawImage2d<'static, u8> {
         let mut image_data = vec![0u8; (self.width * self.height * 4) as usize];
@@ -1,8 +1,11 @@
 #[macro_use]
 extern crate glium;
+extern crate gif;
+extern crate gilrs;
 extern crate rand;
 
 mod gol;
+mod recording;
 mod state;
 mod vertex;
 
@@ -14,8 +17,9 @@ use glium::uniforms::MagnifySamplerFilter::Nearest;
 use glium::uniforms::SamplerWrapFunction::Repeat;
 use glium::{glutin, Display, Surface};
 
+use gilrs::Button as GamepadButton;
 use glutin::dpi::LogicalSize;
-use glutin::VirtualKeyCode;
+use glutin::MouseButton;
 
 use state::{GameState, Tick, GAME_HEIGHT, GAME_WIDTH};
 use vertex::fullscreen;
@@ -70,11 +74,11 @@ fn run(state: &mut GameState) -> Result<ProgramStatus, &'static str> {
     let frame_times_max_size: usize = 10;
     let mut frame_times: VecDeque<SystemTime> = VecDeque::new();
 
-    let mut fullscreen = false;
+    let mut window_size = (WINDOW_WIDTH as u32, WINDOW_HEIGHT as u32);
 
     while state.open {
         frame_times.push_back(SystemTime::now());
-        state.frame();
+        state.frame((window_size.0 as f64, window_size.1 as f64));
 
         if frame_times.len() > frame_times_max_size {
             let start_time = frame_times.pop_front().unwrap();
@@ -83,48 +87,41 @@ fn run(state: &mut GameState) -> Result<ProgramStatus, &'static str> {
                 .unwrap()
                 .as_millis();
             println!(
-                "{:.1} FPS\t{:.2} s\t{} ticks",
+                "{:.1} FPS\t{:.2} s\t{} ticks\t{:.1} Hz",
                 1000.0 / (delay as f32 / frame_times_max_size as f32),
                 state.simulation_time(),
-                state.tick_count
+                state.tick_count,
+                1.0 / state.tick_delay
             );
         }
 
         // Handle input events
-        if state.key_pressed(VirtualKeyCode::Q) {
-            state.open = false;
+        for action in state.pressed_key_actions() {
+            state.execute(action, &display, &events_loop);
         }
 
-        if state.key_pressed(VirtualKeyCode::Space) {
-            state.running = !state.running;
+        for action in state.pressed_gamepad_actions() {
+            state.execute(action, &display, &events_loop);
         }
 
-        if state.key_pressed(VirtualKeyCode::R) {
-            state.send(Tick::Randomize);
-        }
-
-        if state.key_pressed(VirtualKeyCode::S) {
-            state.tick();
-        }
-
-        if state.key_pressed(VirtualKeyCode::G) {
-            state.send(Tick::RandomGlider);
-        }
-
-        if state.key_pressed(VirtualKeyCode::Return) {
-            fullscreen = !fullscreen;
-
-            if fullscreen {
-                let wb = glutin::WindowBuilder::new()
-                    .with_decorations(false)
-                    .with_fullscreen(Some(events_loop.get_primary_monitor()));
-                let cb = glutin::ContextBuilder::new();
-                display.rebuild(wb, cb, &events_loop).unwrap();
-            } else {
-                let wb = glutin::WindowBuilder::new();
-                let cb = glutin::ContextBuilder::new();
-                display.rebuild(wb, cb, &events_loop).unwrap();
-            }
+        if state.mouse_down(MouseButton::Left)
+            || state.mouse_down(MouseButton::Right)
+            || state.gamepad_down(GamepadButton::East)
+        {
+            let (cursor_x, cursor_y) = state.cursor;
+            let board_x = (cursor_x / window_size.0 as f64 * GAME_WIDTH as f64) as i32;
+            let board_y = (cursor_y / window_size.1 as f64 * GAME_HEIGHT as f64) as i32;
+            let board_x = board_x.clamp(0, GAME_WIDTH as i32 - 1);
+            let board_y = board_y.clamp(0, GAME_HEIGHT as i32 - 1);
+
+            let alive =
+                state.mouse_down(MouseButton::Left) || state.gamepad_down(GamepadButton::East);
+
+            state.send(Tick::SetCell {
+                x: board_x,
+                y: board_y,
+                alive: alive,
+            });
         }
 
         // Update texture/uniforms
@@ -153,6 +150,8 @@ fn run(state: &mut GameState) -> Result<ProgramStatus, &'static str> {
             .map_err(|_| "Could not draw shader.")?;
         target.finish().unwrap();
 
+        window_size = dimensions;
+
         events_loop.poll_events(|event| {
             if let glutin::Event::WindowEvent { event, .. } = event {
                 state.consume_event(event);
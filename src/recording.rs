@@ -0,0 +1,52 @@
+use std::fs::File;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use gif::{Encoder, Frame, Repeat};
+use glium::texture::RawImage2d;
+
+/// Encode a sequence of captured frames as a looping animated GIF and write
+/// it to a timestamped file in the current directory.
+pub fn save_gif(frames: &[RawImage2d<'static, u8>], width: u16, height: u16, frame_delay_cs: u16) {
+    if frames.is_empty() {
+        return;
+    }
+
+    let path = format!("recording-{}.gif", unix_timestamp());
+
+    let mut file = match File::create(&path) {
+        Ok(file) => file,
+        Err(e) => {
+            eprintln!("Could not create {}: {}", path, e);
+            return;
+        }
+    };
+
+    let mut encoder = match Encoder::new(&mut file, width, height, &[]) {
+        Ok(encoder) => encoder,
+        Err(e) => {
+            eprintln!("Could not start GIF encoder: {}", e);
+            return;
+        }
+    };
+    let _ = encoder.set_repeat(Repeat::Infinite);
+
+    for frame in frames {
+        let mut rgba = frame.data.clone().into_owned();
+        let mut gif_frame = Frame::from_rgba_speed(width, height, &mut rgba, 10);
+        gif_frame.delay = frame_delay_cs;
+
+        if let Err(e) = encoder.write_frame(&gif_frame) {
+            eprintln!("Could not write GIF frame: {}", e);
+            return;
+        }
+    }
+
+    println!("Saved recording to {}", path);
+}
+
+fn unix_timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
@@ -4,10 +4,63 @@ use std::thread;
 use std::thread::JoinHandle;
 use std::time::SystemTime;
 
-use glium::glutin::{ElementState, MouseButton, VirtualKeyCode, WindowEvent};
+use gilrs::{Axis, Button, EventType, Gilrs};
+use glium::glutin::{
+    ContextBuilder, ElementState, EventsLoop, MouseButton, VirtualKeyCode, WindowBuilder,
+    WindowEvent,
+};
 use glium::texture::RawImage2d;
+use glium::Display;
 
 use super::gol::GoL;
+use super::recording;
+
+/// Pixels per second the cursor moves when the left stick is held at full
+/// deflection, assuming 60 frames per second: each frame moves it by a fixed
+/// 1/60 of this, whatever the actual frame rate.
+const GAMEPAD_CURSOR_SPEED: f64 = 300.0;
+/// Stick deflection below which axis movement is ignored, to avoid drift.
+const GAMEPAD_DEADZONE: f32 = 0.15;
+
+/// Life-like rules cycled through by `Tick::CycleRule`, in B/S notation.
+const RULE_PRESETS: [&str; 3] = [
+    "B3/S23",  // Conway's Game of Life
+    "B36/S23", // HighLife
+    "B2/S",    // Seeds
+];
+
+/// Upper bound on how many generations' hashes are tracked for period
+/// detection before the history is dropped, to bound memory on long runs
+/// that never settle.
+const MAX_TRACKED_STATES: usize = 100_000;
+
+/// Record this generation's alive/dead hash and check whether it matches an
+/// earlier generation. Returns the number of ticks since this state was first
+/// seen, or `None` if it is new. The first `Some` after the history was last
+/// cleared is the period of the still life or oscillator the board has
+/// settled into; later calls return multiples of that period, because a
+/// recurring state keeps its first-seen tick.
+///
+/// The toroidal board means a traveling spaceship eventually returns to its
+/// origin too, so a detected period can legitimately include that orbit.
+fn detect_period(
+    seen_states: &mut HashMap<u64, usize>,
+    game: &GoL,
+    generation: usize,
+) -> Option<usize> {
+    let hash = game.alive_hash();
+
+    if let Some(&first_seen) = seen_states.get(&hash) {
+        return Some(generation - first_seen);
+    }
+
+    if seen_states.len() >= MAX_TRACKED_STATES {
+        seen_states.clear();
+    }
+
+    seen_states.insert(hash, generation);
+    None
+}
 
 pub const GAME_WIDTH: usize = 64;
 pub const GAME_HEIGHT: usize = 64;
@@ -34,7 +87,11 @@ impl ButtonState {
 }
 
 const TICK_RATE: usize = 1; // Hz
-const TICK_DELAY: f32 = 1.0 / TICK_RATE as f32;
+const DEFAULT_TICK_DELAY: f32 = 1.0 / TICK_RATE as f32;
+/// Fastest the simulation can be driven via fast-forward.
+const MIN_TICK_DELAY: f32 = 1.0 / 64.0;
+/// Slowest the simulation can be driven via slow-motion.
+const MAX_TICK_DELAY: f32 = 8.0;
 
 /// Events sent to the GoL simulation thread.
 pub enum Tick {
@@ -42,15 +99,86 @@ pub enum Tick {
     Continue,
     /// Reset and randomize the simulation.
     Randomize,
+    /// Insert a randomly-placed, randomly-oriented glider.
+    InsertGlider,
+    /// Begin capturing each simulated frame for later export.
+    StartRecording,
+    /// Stop capturing frames and write them out as an animated GIF.
+    StopRecording,
+    /// Set a single cell alive or dead, e.g. from mouse-drawn edits.
+    SetCell { x: i32, y: i32, alive: bool },
+    /// Notify the simulation thread that the tick rate has changed, so that
+    /// a recording in progress uses the right frame delay.
+    SetTickDelay(f32),
+    /// Switch to the next rule in `RULE_PRESETS`, keeping the board as-is.
+    CycleRule,
+    /// Whether to randomize the board automatically once a still life or
+    /// oscillator is detected.
+    SetAutoRandomize(bool),
     /// Terminate the simulation
     Quit,
 }
 
+/// A user-facing behavior triggered by an input binding. Centralizing these
+/// means bindings are just data, and the main loop doesn't need to know
+/// what a key "does".
+#[derive(Clone, Copy)]
+pub enum Action {
+    Quit,
+    TogglePause,
+    Randomize,
+    Step,
+    InsertGlider,
+    SpeedUp,
+    SlowDown,
+    ToggleRecording,
+    CycleRule,
+    ToggleAutoRandomize,
+    ToggleFullscreen,
+}
+
+/// Default keyboard bindings, applied in `GameState::new`.
+fn default_key_bindings() -> HashMap<VirtualKeyCode, Action> {
+    let mut bindings = HashMap::new();
+    bindings.insert(VirtualKeyCode::Q, Action::Quit);
+    bindings.insert(VirtualKeyCode::Space, Action::TogglePause);
+    bindings.insert(VirtualKeyCode::R, Action::Randomize);
+    bindings.insert(VirtualKeyCode::S, Action::Step);
+    bindings.insert(VirtualKeyCode::G, Action::InsertGlider);
+    bindings.insert(VirtualKeyCode::RBracket, Action::SpeedUp);
+    bindings.insert(VirtualKeyCode::LBracket, Action::SlowDown);
+    bindings.insert(VirtualKeyCode::V, Action::ToggleRecording);
+    bindings.insert(VirtualKeyCode::L, Action::CycleRule);
+    bindings.insert(VirtualKeyCode::A, Action::ToggleAutoRandomize);
+    bindings.insert(VirtualKeyCode::Return, Action::ToggleFullscreen);
+    bindings
+}
+
+/// Default gamepad bindings, applied in `GameState::new`.
+fn default_gamepad_bindings() -> HashMap<Button, Action> {
+    let mut bindings = HashMap::new();
+    bindings.insert(Button::Start, Action::TogglePause);
+    bindings.insert(Button::West, Action::Randomize);
+    bindings.insert(Button::South, Action::Step);
+    bindings.insert(Button::North, Action::InsertGlider);
+    bindings
+}
+
 pub struct GameState {
     /// Mapping from keys to whether or not they're pressed.
     keyboard: HashMap<VirtualKeyCode, ButtonState>,
     /// Mapping from buttons to whether or not they're pressed.
     mouse: HashMap<MouseButton, ButtonState>,
+    /// Mapping from gamepad buttons to whether or not they're pressed.
+    gamepad_buttons: HashMap<Button, ButtonState>,
+    /// Gamepad input, absent when no controller is connected.
+    gilrs: Option<Gilrs>,
+    /// Current left-stick deflection, used to drive the cursor.
+    left_stick: (f32, f32),
+    /// Mapping from keys to the action they trigger.
+    key_bindings: HashMap<VirtualKeyCode, Action>,
+    /// Mapping from gamepad buttons to the action they trigger.
+    gamepad_bindings: HashMap<Button, Action>,
     /// Cursor location in pixels.
     pub cursor: (f64, f64),
     /// If the window is open.
@@ -63,8 +191,23 @@ pub struct GameState {
     last_tick: f32,
     /// Number of ticks simulated so far.
     pub tick_count: usize,
+    /// Simulated seconds accumulated so far, summed using the tick delay in
+    /// effect at each tick. Kept separate from `tick_count * tick_delay` so
+    /// that changing the tick rate mid-run doesn't retroactively rescale
+    /// already-elapsed time.
+    elapsed_sim_time: f32,
     /// Whether the simulation is running.
     pub running: bool,
+    /// Whether the simulation thread is currently capturing frames to a GIF.
+    pub recording: bool,
+    /// Whether the window is currently fullscreen.
+    pub fullscreen: bool,
+    /// Whether the board should be randomized automatically once it settles
+    /// into a still life or oscillator.
+    pub auto_randomize: bool,
+    /// Seconds between simulated ticks. Mutable so the simulation can be
+    /// fast-forwarded or slowed down at runtime.
+    pub tick_delay: f32,
     /// GoL simulation thread,
     simulation_thread: Option<JoinHandle<()>>,
     /// Sender to provide events to the simulation thread from the main thread.
@@ -81,16 +224,34 @@ impl GameState {
         // after new() is called.
         tick_sender.send(Tick::Continue).unwrap();
 
+        let gilrs = match Gilrs::new() {
+            Ok(gilrs) => Some(gilrs),
+            Err(e) => {
+                eprintln!("Gamepad support disabled: {}", e);
+                None
+            }
+        };
+
         Self {
             keyboard: HashMap::new(),
             mouse: HashMap::new(),
+            gamepad_buttons: HashMap::new(),
+            gilrs: gilrs,
+            left_stick: (0.0, 0.0),
+            key_bindings: default_key_bindings(),
+            gamepad_bindings: default_gamepad_bindings(),
             cursor: (0.0, 0.0),
             open: true,
             start: SystemTime::now(),
             time: 0.0,
             last_tick: 0.0,
             tick_count: 0,
+            elapsed_sim_time: 0.0,
             running: true,
+            recording: false,
+            fullscreen: false,
+            auto_randomize: false,
+            tick_delay: DEFAULT_TICK_DELAY,
             simulation_thread: Some(simulation_thread),
             tick_sender: tick_sender,
             tex_receiver: tex_receiver,
@@ -109,20 +270,101 @@ impl GameState {
             let mut game = GoL::new((GAME_WIDTH, GAME_HEIGHT));
             game.randomize();
 
+            let mut captured_frames: Option<Vec<RawImage2d<'static, u8>>> = None;
+            let mut tick_delay = DEFAULT_TICK_DELAY;
+            let mut rule_index = 0;
+            let mut auto_randomize = false;
+            let mut generation: usize = 0;
+            let mut seen_states: HashMap<u64, usize> = HashMap::new();
+            // Whether the current settle has already been reported, so a
+            // still life/oscillator prints once instead of every tick.
+            let mut settled = false;
+
             loop {
                 use Tick::*;
 
                 match tick_receiver.recv() {
                     Ok(Continue) => {
                         game.step();
-                        let texture = game.as_raw_image_2d();
-                        tex_sender.send(texture).unwrap();
+                        generation += 1;
+
+                        if let Some(period) = detect_period(&mut seen_states, &game, generation) {
+                            if !settled {
+                                println!("Settled, period {}", period);
+                                settled = true;
+                            }
+
+                            if auto_randomize {
+                                game.randomize();
+                                generation = 0;
+                                seen_states.clear();
+                                settled = false;
+                            }
+                        }
+
+                        if let Some(frames) = captured_frames.as_mut() {
+                            frames.push(game.as_raw_image_2d());
+                        }
+
+                        tex_sender.send(game.as_raw_image_2d()).unwrap();
                     }
                     Ok(Randomize) => {
                         game.randomize();
+                        generation = 0;
+                        seen_states.clear();
+                        settled = false;
+                        let texture = game.as_raw_image_2d();
+                        tex_sender.send(texture).unwrap();
+                    }
+                    Ok(InsertGlider) => {
+                        game.insert_glider();
+                        generation = 0;
+                        seen_states.clear();
+                        settled = false;
                         let texture = game.as_raw_image_2d();
                         tex_sender.send(texture).unwrap();
                     }
+                    Ok(StartRecording) => {
+                        captured_frames = Some(Vec::new());
+                    }
+                    Ok(StopRecording) => {
+                        if let Some(frames) = captured_frames.take() {
+                            let frame_delay_cs = (tick_delay * 100.0) as u16;
+                            recording::save_gif(
+                                &frames,
+                                GAME_WIDTH as u16,
+                                GAME_HEIGHT as u16,
+                                frame_delay_cs,
+                            );
+                        }
+                    }
+                    Ok(SetTickDelay(delay)) => {
+                        tick_delay = delay;
+                    }
+                    Ok(CycleRule) => {
+                        rule_index = (rule_index + 1) % RULE_PRESETS.len();
+                        let rule = RULE_PRESETS[rule_index];
+
+                        if let Err(e) = game.set_rule(rule) {
+                            eprintln!("Could not switch to rule \"{}\": {}", rule, e);
+                        } else {
+                            println!("Switched to rule {}", rule);
+                            generation = 0;
+                            seen_states.clear();
+                            settled = false;
+                        }
+                    }
+                    Ok(SetCell { x, y, alive }) => {
+                        game[(x, y)] = alive as u8;
+                        generation = 0;
+                        seen_states.clear();
+                        settled = false;
+                        let texture = game.as_raw_image_2d();
+                        tex_sender.send(texture).unwrap();
+                    }
+                    Ok(SetAutoRandomize(enabled)) => {
+                        auto_randomize = enabled;
+                    }
                     Ok(Quit) => {
                         return;
                     }
@@ -177,12 +419,166 @@ impl GameState {
         false
     }
 
+    /// Whether a button on the gamepad is currently pressed.
+    pub fn gamepad_down(&self, button: Button) -> bool {
+        self.gamepad_buttons
+            .get(&button)
+            .map(|ButtonState { down, .. }| *down)
+            .unwrap_or(false)
+    }
+
+    /// Whether a button on the gamepad is newly pressed.
+    pub fn gamepad_pressed(&mut self, button: Button) -> bool {
+        if let Some(state) = self.gamepad_buttons.get_mut(&button) {
+            if !state.seen {
+                state.seen = true;
+                return state.down;
+            }
+        }
+
+        false
+    }
+
     pub fn send(&self, tick: Tick) {
         self.tick_sender.send(tick).unwrap();
     }
 
+    /// Actions newly triggered by bound keys this frame, in the same
+    /// pressed-vs-seen sense as `key_pressed`.
+    pub fn pressed_key_actions(&mut self) -> Vec<Action> {
+        let keys: Vec<VirtualKeyCode> = self.key_bindings.keys().cloned().collect();
+        let mut actions = Vec::new();
+
+        for key in keys {
+            if self.key_pressed(key) {
+                actions.push(self.key_bindings[&key]);
+            }
+        }
+
+        actions
+    }
+
+    /// Actions newly triggered by bound gamepad buttons this frame.
+    pub fn pressed_gamepad_actions(&mut self) -> Vec<Action> {
+        let buttons: Vec<Button> = self.gamepad_bindings.keys().cloned().collect();
+        let mut actions = Vec::new();
+
+        for button in buttons {
+            if self.gamepad_pressed(button) {
+                actions.push(self.gamepad_bindings[&button]);
+            }
+        }
+
+        actions
+    }
+
+    /// Apply the effect of a single action.
+    pub fn execute(&mut self, action: Action, display: &Display, events_loop: &EventsLoop) {
+        match action {
+            Action::Quit => {
+                self.open = false;
+            }
+            Action::TogglePause => {
+                self.running = !self.running;
+            }
+            Action::Randomize => {
+                self.send(Tick::Randomize);
+            }
+            Action::Step => {
+                self.tick();
+            }
+            Action::InsertGlider => {
+                self.send(Tick::InsertGlider);
+            }
+            Action::SpeedUp => {
+                self.speed_up();
+            }
+            Action::SlowDown => {
+                self.slow_down();
+            }
+            Action::ToggleRecording => {
+                if self.recording {
+                    self.send(Tick::StopRecording);
+                } else {
+                    self.send(Tick::StartRecording);
+                }
+                self.recording = !self.recording;
+            }
+            Action::CycleRule => {
+                self.send(Tick::CycleRule);
+            }
+            Action::ToggleAutoRandomize => {
+                self.auto_randomize = !self.auto_randomize;
+                self.send(Tick::SetAutoRandomize(self.auto_randomize));
+            }
+            Action::ToggleFullscreen => {
+                self.fullscreen = !self.fullscreen;
+
+                let mut wb = WindowBuilder::new();
+                if self.fullscreen {
+                    wb = wb
+                        .with_decorations(false)
+                        .with_fullscreen(Some(events_loop.get_primary_monitor()));
+                }
+                let cb = ContextBuilder::new();
+                display.rebuild(wb, cb, events_loop).unwrap();
+            }
+        }
+    }
+
+    /// Drain pending gamepad events, updating button state the same way
+    /// keyboard/mouse events do, and drive the cursor from the left stick,
+    /// clamped to the window so it stays a valid draw target.
+    fn poll_gamepad(&mut self, window_size: (f64, f64)) {
+        if let Some(gilrs) = self.gilrs.as_mut() {
+            while let Some(event) = gilrs.next_event() {
+                match event.event {
+                    EventType::ButtonPressed(button, _) => {
+                        let current_down = self
+                            .gamepad_buttons
+                            .get(&button)
+                            .map(|ButtonState { down, .. }| *down)
+                            .unwrap_or(false);
+                        self.gamepad_buttons
+                            .insert(button, ButtonState::new(ElementState::Pressed, current_down));
+                    }
+                    EventType::ButtonReleased(button, _) => {
+                        let current_down = self
+                            .gamepad_buttons
+                            .get(&button)
+                            .map(|ButtonState { down, .. }| *down)
+                            .unwrap_or(false);
+                        self.gamepad_buttons.insert(
+                            button,
+                            ButtonState::new(ElementState::Released, current_down),
+                        );
+                    }
+                    EventType::AxisChanged(Axis::LeftStickX, value, _) => {
+                        self.left_stick.0 = value;
+                    }
+                    EventType::AxisChanged(Axis::LeftStickY, value, _) => {
+                        self.left_stick.1 = value;
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        if self.left_stick.0.abs() > GAMEPAD_DEADZONE || self.left_stick.1.abs() > GAMEPAD_DEADZONE
+        {
+            // Roughly per-frame movement; fine-grained enough for drawing.
+            self.cursor.0 += self.left_stick.0 as f64 * GAMEPAD_CURSOR_SPEED / 60.0;
+            self.cursor.1 -= self.left_stick.1 as f64 * GAMEPAD_CURSOR_SPEED / 60.0;
+        }
+
+        self.cursor.0 = self.cursor.0.clamp(0.0, window_size.0);
+        self.cursor.1 = self.cursor.1.clamp(0.0, window_size.1);
+    }
+
     /// Updates to state ran per-frame.
-    pub fn frame(&mut self) {
+    pub fn frame(&mut self, window_size: (f64, f64)) {
+        self.poll_gamepad(window_size);
+
         let time_millis = SystemTime::now()
             .duration_since(self.start)
             .unwrap()
@@ -192,17 +588,33 @@ impl GameState {
         // Run next simulation frame if enough time has passed
         if self.running || self.key_down(VirtualKeyCode::C) {
             while self.last_tick < self.time {
-                self.last_tick += TICK_DELAY;
+                self.last_tick += self.tick_delay;
                 self.tick();
             }
         }
     }
 
+    /// Double the simulation's tick rate (fast-forward), up to `MIN_TICK_DELAY`.
+    pub fn speed_up(&mut self) {
+        self.set_tick_delay(self.tick_delay / 2.0);
+    }
+
+    /// Halve the simulation's tick rate (slow-motion), down to `MAX_TICK_DELAY`.
+    pub fn slow_down(&mut self) {
+        self.set_tick_delay(self.tick_delay * 2.0);
+    }
+
+    fn set_tick_delay(&mut self, delay: f32) {
+        self.tick_delay = delay.clamp(MIN_TICK_DELAY, MAX_TICK_DELAY);
+        self.send(Tick::SetTickDelay(self.tick_delay));
+    }
+
     /// Updates to state ran per-tick.
     pub fn tick(&mut self) {
         match self.tick_sender.try_send(Tick::Continue) {
             Ok(()) => {
                 self.tick_count += 1;
+                self.elapsed_sim_time += self.tick_delay;
             }
             Err(_) => {
                 eprintln!("Failed to send tick to simulation_thread");
@@ -215,12 +627,12 @@ impl GameState {
         let delta = self.time - self.last_tick;
         let interpolation = if !self.running {
             0f32
-        } else if delta < TICK_DELAY {
+        } else if delta < self.tick_delay {
             delta
         } else {
-            TICK_DELAY
+            self.tick_delay
         };
-        return self.tick_count as f32 * TICK_DELAY + interpolation;
+        return self.elapsed_sim_time + interpolation;
     }
 
     /// Apply an event's changes to state.